@@ -1,6 +1,6 @@
 use defmt::trace;
 
-use crate::pdo::VDMHeader;
+use crate::pdo::{VDMCommand, VDMCommandType, VDMHeader, VDMType};
 
 use {
     crate::{
@@ -21,8 +21,8 @@ pub enum Message {
     Accept,
     Reject,
     Ready,
-    SourceCapabilities(Vec<PowerDataObject, 8>),
-    VendorDefined(VDMHeader),  // TODO: Incomplete
+    SourceCapabilities(SourceCapabilities),
+    VendorDefined((VDMHeader, Vec<u32, 7>)),
     SoftReset,
     Unknown,
 }
@@ -34,56 +34,67 @@ impl Message {
             MessageType::Control(ControlMessageType::Reject) => Message::Reject,
             MessageType::Control(ControlMessageType::PsRdy) => Message::Ready,
             MessageType::Control(ControlMessageType::SoftReset) => Message::SoftReset,
-            MessageType::Data(DataMessageType::SourceCapabilities) => Message::SourceCapabilities(
-                payload
-                    .chunks_exact(4)
-                    .take(header.num_objects())
-                    .map(|buf| PowerDataObjectRaw(LittleEndian::read_u32(buf)))
-                    .map(|pdo| match pdo.kind() {
-                        0b00 => PowerDataObject::FixedSupply(FixedSupply(pdo.0)),
-                        0b01 => PowerDataObject::Battery(Battery(pdo.0)),
-                        0b10 => PowerDataObject::VariableSupply(VariableSupply(pdo.0)),
-                        0b11 => PowerDataObject::AugmentedPowerDataObject({
-                            match AugmentedPowerDataObjectRaw(pdo.0).supply() {
-                                0b00 => {
-                                    AugmentedPowerDataObject::SPR(SPRProgrammablePowerSupply(pdo.0))
-                                }
-                                0b01 => {
-                                    AugmentedPowerDataObject::EPR(EPRAdjustableVoltageSupply(pdo.0))
+            MessageType::Data(DataMessageType::SourceCapabilities) => {
+                Message::SourceCapabilities(SourceCapabilities(
+                    payload
+                        .chunks_exact(4)
+                        .take(header.num_objects())
+                        .map(|buf| PowerDataObjectRaw(LittleEndian::read_u32(buf)))
+                        .map(|pdo| match pdo.kind() {
+                            0b00 => PowerDataObject::FixedSupply(FixedSupply(pdo.0)),
+                            0b01 => PowerDataObject::Battery(Battery(pdo.0)),
+                            0b10 => PowerDataObject::VariableSupply(VariableSupply(pdo.0)),
+                            0b11 => PowerDataObject::AugmentedPowerDataObject({
+                                match AugmentedPowerDataObjectRaw(pdo.0).supply() {
+                                    0b00 => AugmentedPowerDataObject::SPR(
+                                        SPRProgrammablePowerSupply(pdo.0),
+                                    ),
+                                    0b01 => AugmentedPowerDataObject::EPR(
+                                        EPRAdjustableVoltageSupply(pdo.0),
+                                    ),
+                                    _ => unreachable!(),
                                 }
-                                _ => unreachable!(),
-                            }
-                        }),
-                        _ => unreachable!(),
-                    })
-                    .collect(),
-            ),
+                            }),
+                            _ => unreachable!(),
+                        })
+                        .collect(),
+                ))
+            }
             MessageType::Data(DataMessageType::VendorDefined) => {
-                // Keep for now...
-                // let len = payload.len();
-                // let num_obj = header.num_objects();
-                //debug!("VENDOR: {:?}, {:?}, {:?}", len, num_obj, payload);
-
-                let header = payload
-                .chunks_exact(4)
-                .take(1)
-                .map(|h| {
-                    VDMHeader(LittleEndian::read_u32(h))
-                })
-                .next().unwrap();
+                let Some(vdm_header) = payload
+                    .chunks_exact(4)
+                    .take(1)
+                    .map(|h| VDMHeader(LittleEndian::read_u32(h)))
+                    .next()
+                else {
+                    warn!("truncated VDM");
+                    return Message::Unknown;
+                };
 
                 trace!("VDM RX:");
-                trace!("HEADER: VDM:: TYPE: {:?}, VERS: {:?}", header.vdm_type(), header.vdm_version());
-                trace!("HEADER: CMD:: TYPE: {:?}, CMD: {:?}", header.command_type(), header.command());
+                trace!(
+                    "HEADER: VDM:: TYPE: {:?}, VERS: {:?}",
+                    vdm_header.vdm_type(),
+                    vdm_header.vdm_version()
+                );
+                trace!(
+                    "HEADER: CMD:: TYPE: {:?}, CMD: {:?}",
+                    vdm_header.command_type(),
+                    vdm_header.command()
+                );
 
-                // Keep for now...
-                // let pkt = payload
-                //     .chunks_exact(1)
-                //     .take(8)
-                //     .map(|i| i[0])
-                //     .collect::<Vec<u8, 8>>();
+                // Unstructured VDMs (vdm_type == Unstructured) don't carry a command we can
+                // interpret generically, so their data objects are not collected.
+                let vdos = match vdm_header.vdm_type() {
+                    VDMType::Structured => payload[4..]
+                        .chunks_exact(4)
+                        .take(header.num_objects().saturating_sub(1))
+                        .map(LittleEndian::read_u32)
+                        .collect(),
+                    VDMType::Unstructured => Vec::new(),
+                };
 
-                Message::VendorDefined(header)
+                Message::VendorDefined((vdm_header, vdos))
             }
             _ => {
                 warn!("unknown message type");
@@ -92,3 +103,195 @@ impl Message {
         }
     }
 }
+
+/// A source's advertised set of Power Data Objects, with convenience queries over the raw
+/// encoding so callers can work in engineering units instead of hand-rolled multiples.
+#[derive(Clone, Format)]
+pub struct SourceCapabilities(pub Vec<PowerDataObject, 8>);
+
+impl SourceCapabilities {
+    /// The advertised Power Data Objects, in the order the source sent them.
+    pub fn pdos(&self) -> &[PowerDataObject] {
+        &self.0
+    }
+
+    /// The highest voltage offered by a fixed supply, in mV.
+    pub fn max_fixed_voltage_mv(&self) -> Option<u16> {
+        self.pdos()
+            .iter()
+            .filter_map(|pdo| match pdo {
+                PowerDataObject::FixedSupply(supply) => Some(supply.voltage_mv()),
+                _ => None,
+            })
+            .max()
+    }
+
+    /// Whether the source supports dual-role power, per the first Fixed Supply PDO (always
+    /// object position 1, per the USB PD spec).
+    pub fn dual_role_power(&self) -> bool {
+        self.vsafe5v()
+            .is_some_and(|supply| supply.dual_role_power())
+    }
+
+    /// Whether the source is USB communications capable, per the first Fixed Supply PDO.
+    pub fn usb_communications_capable(&self) -> bool {
+        self.vsafe5v()
+            .is_some_and(|supply| supply.usb_communications_capable())
+    }
+
+    /// Decodes the source capability flags carried by the first Fixed Supply PDO (`None` if the
+    /// source didn't advertise one, which shouldn't happen in practice).
+    pub fn flags(&self) -> Option<SourceCapabilityFlags> {
+        self.vsafe5v().map(|supply| SourceCapabilityFlags {
+            dual_role_power: supply.dual_role_power(),
+            usb_suspend_supported: supply.usb_suspend_supported(),
+            unconstrained_power: supply.unconstrained_power(),
+            usb_communications_capable: supply.usb_communications_capable(),
+            dual_role_data: supply.dual_role_data(),
+        })
+    }
+
+    fn vsafe5v(&self) -> Option<&FixedSupply> {
+        match self.pdos().first() {
+            Some(PowerDataObject::FixedSupply(supply)) => Some(supply),
+            _ => None,
+        }
+    }
+}
+
+/// Source capability flags carried by the first Fixed Supply PDO of a `SourceCapabilities`
+/// message, letting clients tell e.g. an unconstrained source (safe to draw full power
+/// immediately) from a battery-backed dual-role partner.
+#[derive(Clone, Copy, Format)]
+pub struct SourceCapabilityFlags {
+    pub dual_role_power: bool,
+    pub usb_suspend_supported: bool,
+    pub unconstrained_power: bool,
+    pub usb_communications_capable: bool,
+    pub dual_role_data: bool,
+}
+
+/// ID Header VDO, the first data object of a Discover Identity ACK.
+#[derive(Clone, Copy, Format)]
+pub struct IdHeaderVDO(pub u32);
+
+impl IdHeaderVDO {
+    /// USB Vendor ID assigned by USB-IF.
+    pub fn usb_vendor_id(&self) -> u16 {
+        (self.0 & 0xffff) as u16
+    }
+
+    /// Whether the responder supports Modal Operation (has one or more SVIDs with modes).
+    pub fn modal_operation_supported(&self) -> bool {
+        (self.0 >> 26) & 0b1 != 0
+    }
+
+    /// Product type (UFP/DFP/Cable, encoding depends on port data role of the responder).
+    pub fn product_type(&self) -> u8 {
+        ((self.0 >> 27) & 0b111) as u8
+    }
+}
+
+/// Cert Stat VDO, the second data object of a Discover Identity ACK.
+#[derive(Clone, Copy, Format)]
+pub struct CertStatVDO(pub u32);
+
+impl CertStatVDO {
+    /// USB-IF assigned XID for the product.
+    pub fn xid(&self) -> u32 {
+        self.0
+    }
+}
+
+/// Product VDO, the third data object of a Discover Identity ACK.
+#[derive(Clone, Copy, Format)]
+pub struct ProductVDO(pub u32);
+
+impl ProductVDO {
+    /// USB Product ID assigned by the vendor.
+    pub fn usb_product_id(&self) -> u16 {
+        (self.0 >> 16) as u16
+    }
+
+    /// Vendor-defined device release number (binary-coded decimal).
+    pub fn bcd_device(&self) -> u16 {
+        (self.0 & 0xffff) as u16
+    }
+}
+
+/// Decoded response to a Discover Identity request.
+#[derive(Clone, Format)]
+pub struct Identity {
+    pub id_header: IdHeaderVDO,
+    pub cert_stat: CertStatVDO,
+    pub product: ProductVDO,
+}
+
+impl Identity {
+    /// Parses a Discover Identity ACK, returning `None` if it isn't one.
+    pub fn parse(header: &VDMHeader, vdos: &[u32]) -> Option<Self> {
+        if header.command() != VDMCommand::DiscoverIdentity
+            || header.command_type() != VDMCommandType::Ack
+        {
+            return None;
+        }
+
+        let [id_header, cert_stat, product, ..] = vdos else {
+            return None;
+        };
+
+        Some(Identity {
+            id_header: IdHeaderVDO(*id_header),
+            cert_stat: CertStatVDO(*cert_stat),
+            product: ProductVDO(*product),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn discover_identity_ack_header() -> VDMHeader {
+        VDMHeader(0)
+            .with_vdm_type(VDMType::Structured)
+            .with_command_type(VDMCommandType::Ack)
+            .with_command(VDMCommand::DiscoverIdentity)
+    }
+
+    #[test]
+    fn parse_rejects_too_few_vdos() {
+        let header = discover_identity_ack_header();
+
+        assert!(Identity::parse(&header, &[]).is_none());
+        assert!(Identity::parse(&header, &[1, 2]).is_none());
+    }
+
+    #[test]
+    fn parse_rejects_wrong_command() {
+        let header = VDMHeader(0)
+            .with_vdm_type(VDMType::Structured)
+            .with_command_type(VDMCommandType::Ack)
+            .with_command(VDMCommand::DiscoverSvids);
+
+        assert!(Identity::parse(&header, &[1, 2, 3]).is_none());
+    }
+
+    #[test]
+    fn parse_rejects_non_ack() {
+        let header = VDMHeader(0)
+            .with_vdm_type(VDMType::Structured)
+            .with_command_type(VDMCommandType::Req)
+            .with_command(VDMCommand::DiscoverIdentity);
+
+        assert!(Identity::parse(&header, &[1, 2, 3]).is_none());
+    }
+
+    #[test]
+    fn parse_accepts_exact_and_extra_vdos() {
+        let header = discover_identity_ack_header();
+
+        assert!(Identity::parse(&header, &[1, 2, 3]).is_some());
+        assert!(Identity::parse(&header, &[1, 2, 3, 4]).is_some());
+    }
+}
@@ -0,0 +1,297 @@
+//! Power Data Object (PDO), Request Data Object (RDO) and Vendor Defined Message (VDM) header
+//! encodings, as carried in Source Capabilities/Request/VDM message payloads.
+
+use byteorder::{ByteOrder, LittleEndian};
+use defmt::Format;
+
+/// Raw, not-yet-classified encoding of a Power Data Object.
+#[derive(Clone, Copy)]
+pub struct PowerDataObjectRaw(pub u32);
+
+impl PowerDataObjectRaw {
+    /// The PDO kind discriminant (bits 31:30): `0b00` Fixed, `0b01` Battery, `0b10` Variable,
+    /// `0b11` Augmented.
+    pub fn kind(&self) -> u8 {
+        ((self.0 >> 30) & 0b11) as u8
+    }
+}
+
+/// A classified Power Data Object, as advertised in a Source Capabilities message.
+#[derive(Clone, Format)]
+pub enum PowerDataObject {
+    FixedSupply(FixedSupply),
+    Battery(Battery),
+    VariableSupply(VariableSupply),
+    AugmentedPowerDataObject(AugmentedPowerDataObject),
+}
+
+/// Fixed Supply Power Data Object.
+#[derive(Clone, Copy, Format)]
+pub struct FixedSupply(pub u32);
+
+impl FixedSupply {
+    /// Voltage, in mV (bits 19:10, 50mV units).
+    pub fn voltage_mv(&self) -> u16 {
+        (((self.0 >> 10) & 0x3ff) * 50) as u16
+    }
+
+    /// Maximum current, in mA (bits 9:0, 10mA units).
+    pub fn max_current_ma(&self) -> u16 {
+        ((self.0 & 0x3ff) * 10) as u16
+    }
+
+    /// Dual-Role Power (bit 29).
+    pub fn dual_role_power(&self) -> bool {
+        (self.0 >> 29) & 0b1 != 0
+    }
+
+    /// USB Suspend Supported (bit 28).
+    pub fn usb_suspend_supported(&self) -> bool {
+        (self.0 >> 28) & 0b1 != 0
+    }
+
+    /// Unconstrained Power (bit 27).
+    pub fn unconstrained_power(&self) -> bool {
+        (self.0 >> 27) & 0b1 != 0
+    }
+
+    /// USB Communications Capable (bit 26).
+    pub fn usb_communications_capable(&self) -> bool {
+        (self.0 >> 26) & 0b1 != 0
+    }
+
+    /// Dual-Role Data (bit 25).
+    pub fn dual_role_data(&self) -> bool {
+        (self.0 >> 25) & 0b1 != 0
+    }
+}
+
+/// Battery Power Data Object.
+#[derive(Clone, Copy, Format)]
+pub struct Battery(pub u32);
+
+/// Variable Supply Power Data Object.
+#[derive(Clone, Copy, Format)]
+pub struct VariableSupply(pub u32);
+
+/// Raw, not-yet-classified encoding of an Augmented Power Data Object.
+#[derive(Clone, Copy)]
+pub struct AugmentedPowerDataObjectRaw(pub u32);
+
+impl AugmentedPowerDataObjectRaw {
+    /// The APDO supply discriminant (bits 29:28): `0b00` SPR PPS, `0b01` EPR AVS.
+    pub fn supply(&self) -> u8 {
+        ((self.0 >> 28) & 0b11) as u8
+    }
+}
+
+/// A classified Augmented Power Data Object.
+#[derive(Clone, Format)]
+pub enum AugmentedPowerDataObject {
+    SPR(SPRProgrammablePowerSupply),
+    EPR(EPRAdjustableVoltageSupply),
+}
+
+/// SPR Programmable Power Supply (PPS) Augmented Power Data Object.
+#[derive(Clone, Copy, Format)]
+pub struct SPRProgrammablePowerSupply(pub u32);
+
+impl SPRProgrammablePowerSupply {
+    /// Minimum voltage, in mV (bits 15:8, 100mV units).
+    pub fn min_voltage_mv(&self) -> u16 {
+        (((self.0 >> 8) & 0xff) * 100) as u16
+    }
+
+    /// Maximum voltage, in mV (bits 24:17, 100mV units).
+    pub fn max_voltage_mv(&self) -> u16 {
+        (((self.0 >> 17) & 0xff) * 100) as u16
+    }
+
+    /// Maximum current, in mA (bits 6:0, 50mA units).
+    pub fn max_current_ma(&self) -> u16 {
+        ((self.0 & 0x7f) * 50) as u16
+    }
+}
+
+/// EPR Adjustable Voltage Supply Augmented Power Data Object.
+#[derive(Clone, Copy, Format)]
+pub struct EPRAdjustableVoltageSupply(pub u32);
+
+/// Fixed/Variable Supply Request Data Object, as sent in a Request message.
+#[derive(Clone, Copy)]
+pub struct FixedVariableRequestDataObject(pub u32);
+
+impl FixedVariableRequestDataObject {
+    pub fn with_object_position(self, object_position: u8) -> Self {
+        Self((self.0 & !(0xf << 28)) | ((object_position as u32 & 0xf) << 28))
+    }
+
+    pub fn with_usb_communications_capable(self, capable: bool) -> Self {
+        Self((self.0 & !(1 << 25)) | ((capable as u32) << 25))
+    }
+
+    pub fn with_no_usb_suspend(self, no_suspend: bool) -> Self {
+        Self((self.0 & !(1 << 24)) | ((no_suspend as u32) << 24))
+    }
+
+    pub fn with_operating_current(self, current: u16) -> Self {
+        Self((self.0 & !(0x3ff << 10)) | ((current as u32 & 0x3ff) << 10))
+    }
+
+    pub fn with_maximum_operating_current(self, current: u16) -> Self {
+        Self((self.0 & !0x3ff) | (current as u32 & 0x3ff))
+    }
+
+    pub fn object_position(&self) -> u8 {
+        ((self.0 >> 28) & 0xf) as u8
+    }
+
+    pub fn operating_current(&self) -> u16 {
+        ((self.0 >> 10) & 0x3ff) as u16
+    }
+
+    pub fn maximum_operating_current(&self) -> u16 {
+        (self.0 & 0x3ff) as u16
+    }
+
+    pub fn to_bytes(self, payload: &mut [u8]) {
+        LittleEndian::write_u32(payload, self.0);
+    }
+}
+
+/// Programmable Request Data Object, as sent in a Request message for a PPS output.
+#[derive(Clone, Copy)]
+pub struct ProgrammableRequestDataObject(pub u32);
+
+impl ProgrammableRequestDataObject {
+    pub fn with_object_position(self, object_position: u8) -> Self {
+        Self((self.0 & !(0xf << 28)) | ((object_position as u32 & 0xf) << 28))
+    }
+
+    pub fn with_usb_communications_capable(self, capable: bool) -> Self {
+        Self((self.0 & !(1 << 25)) | ((capable as u32) << 25))
+    }
+
+    pub fn with_no_usb_suspend(self, no_suspend: bool) -> Self {
+        Self((self.0 & !(1 << 24)) | ((no_suspend as u32) << 24))
+    }
+
+    pub fn with_output_voltage(self, voltage: u16) -> Self {
+        Self((self.0 & !(0x7ff << 9)) | ((voltage as u32 & 0x7ff) << 9))
+    }
+
+    pub fn with_operating_current(self, current: u16) -> Self {
+        Self((self.0 & !0x7f) | (current as u32 & 0x7f))
+    }
+
+    pub fn object_position(&self) -> u8 {
+        ((self.0 >> 28) & 0xf) as u8
+    }
+
+    pub fn output_voltage(&self) -> u16 {
+        ((self.0 >> 9) & 0x7ff) as u16
+    }
+
+    pub fn operating_current(&self) -> u16 {
+        (self.0 & 0x7f) as u16
+    }
+
+    pub fn to_bytes(self, payload: &mut [u8]) {
+        LittleEndian::write_u32(payload, self.0);
+    }
+}
+
+/// Whether a VDM header carries a Structured or Unstructured VDM.
+#[derive(Clone, Copy, PartialEq, Eq, Format)]
+pub enum VDMType {
+    Unstructured = 0,
+    Structured = 1,
+}
+
+/// Structured VDM command type (bits 7:6 of the VDM header).
+#[derive(Clone, Copy, PartialEq, Eq, Format)]
+pub enum VDMCommandType {
+    Req = 0b00,
+    Ack = 0b01,
+    Nak = 0b10,
+    Busy = 0b11,
+}
+
+/// Structured VDM command (bits 4:0 of the VDM header).
+#[derive(Clone, Copy, PartialEq, Eq, Format)]
+pub enum VDMCommand {
+    DiscoverIdentity = 1,
+    DiscoverSvids = 2,
+    DiscoverModes = 3,
+    EnterMode = 4,
+    ExitMode = 5,
+    Attention = 6,
+    Unknown = 0,
+}
+
+impl From<u8> for VDMCommand {
+    fn from(value: u8) -> Self {
+        match value {
+            1 => VDMCommand::DiscoverIdentity,
+            2 => VDMCommand::DiscoverSvids,
+            3 => VDMCommand::DiscoverModes,
+            4 => VDMCommand::EnterMode,
+            5 => VDMCommand::ExitMode,
+            6 => VDMCommand::Attention,
+            _ => VDMCommand::Unknown,
+        }
+    }
+}
+
+/// Vendor Defined Message header, the first object of every VDM.
+#[derive(Clone, Copy, Format)]
+pub struct VDMHeader(pub u32);
+
+impl VDMHeader {
+    pub fn with_svid(self, svid: u16) -> Self {
+        Self((self.0 & 0xffff) | ((svid as u32) << 16))
+    }
+
+    pub fn with_vdm_type(self, vdm_type: VDMType) -> Self {
+        Self((self.0 & !(1 << 15)) | ((vdm_type as u32) << 15))
+    }
+
+    pub fn with_command_type(self, command_type: VDMCommandType) -> Self {
+        Self((self.0 & !(0b11 << 6)) | ((command_type as u32) << 6))
+    }
+
+    pub fn with_command(self, command: VDMCommand) -> Self {
+        Self((self.0 & !0x1f) | (command as u32 & 0x1f))
+    }
+
+    /// USB-IF assigned Standard or Vendor ID (bits 31:16).
+    pub fn svid(&self) -> u16 {
+        (self.0 >> 16) as u16
+    }
+
+    pub fn vdm_type(&self) -> VDMType {
+        if (self.0 >> 15) & 0b1 != 0 {
+            VDMType::Structured
+        } else {
+            VDMType::Unstructured
+        }
+    }
+
+    /// Structured VDM Version (bits 14:13).
+    pub fn vdm_version(&self) -> u8 {
+        ((self.0 >> 13) & 0b11) as u8
+    }
+
+    pub fn command_type(&self) -> VDMCommandType {
+        match (self.0 >> 6) & 0b11 {
+            0b00 => VDMCommandType::Req,
+            0b01 => VDMCommandType::Ack,
+            0b10 => VDMCommandType::Nak,
+            _ => VDMCommandType::Busy,
+        }
+    }
+
+    pub fn command(&self) -> VDMCommand {
+        VDMCommand::from((self.0 & 0x1f) as u8)
+    }
+}
@@ -1,14 +1,201 @@
 use {
     crate::{
-        header::{DataMessageType, Header, SpecificationRevision},
-        message::Message,
-        pdo::{FixedVariableRequestDataObject, PowerDataObject},
+        header::{ControlMessageType, DataMessageType, Header, SpecificationRevision},
+        message::{Identity, Message, SourceCapabilities, SourceCapabilityFlags},
+        pdo::{
+            AugmentedPowerDataObject, FixedVariableRequestDataObject, PowerDataObject,
+            ProgrammableRequestDataObject, VDMCommand, VDMCommandType, VDMHeader, VDMType,
+        },
         Instant, PowerRole,
     },
+    byteorder::{ByteOrder, LittleEndian},
     defmt::{debug, trace},
-    heapless::Vec,
 };
 
+/// Milliseconds elapsed between two `Instant`s, saturating at zero if `now` precedes `earlier`.
+fn elapsed_ms(now: Instant, earlier: Instant) -> u64 {
+    now.checked_duration_since(earlier)
+        .map_or(0, |d| d.as_millis() as u64)
+}
+
+/// Whether `caps.pdos()[index]` is a PPS APDO whose advertised range contains `voltage_mv`. Used
+/// both to validate a `RequestPolicy`-supplied index and to search for one by voltage, so a
+/// custom policy can't request a PPS output that's out of range or doesn't exist.
+fn pps_in_range(caps: &SourceCapabilities, index: usize, voltage_mv: u16) -> bool {
+    matches!(
+        caps.pdos().get(index),
+        Some(PowerDataObject::AugmentedPowerDataObject(AugmentedPowerDataObject::SPR(pps)))
+            if voltage_mv >= pps.min_voltage_mv() && voltage_mv <= pps.max_voltage_mv()
+    )
+}
+
+/// Standard SVID for Discover Identity/SVIDs/Modes commands.
+const SVID_DISCOVERY: u16 = 0xff00;
+
+/// Interval at which a PPS request must be repeated to keep the source from reverting to 5V.
+const PPS_REQUEST_INTERVAL_MS: u64 = 8_000;
+
+/// A PPS request that needs to be kept alive with periodic re-requests.
+#[derive(Clone, Copy)]
+struct PpsRequest {
+    index: u8,
+    voltage_mv: u16,
+    current_ma: u16,
+}
+
+/// The last Request we sent, kept around so it can be resent verbatim on a timeout.
+#[derive(Clone, Copy)]
+enum LastRequest {
+    Fixed {
+        index: u8,
+        voltage_mv: u16,
+        current_ma: u16,
+    },
+    Pps(PpsRequest),
+}
+
+/// SinkWaitCap timer: how long to wait for SourceCapabilities after entering `UsbPd`.
+const SINK_WAIT_CAP_TIMEOUT_MS: u64 = 620;
+
+/// SenderResponse timer: how long to wait for Accept/Reject after sending a Request.
+const SENDER_RESPONSE_TIMEOUT_MS: u64 = 27;
+
+/// PSTransition timer: how long to wait for PS_RDY after an Accept.
+const PS_TRANSITION_TIMEOUT_MS: u64 = 550;
+
+/// Matches the FUSB302 driver's own retry count before giving up and resetting.
+const MAX_RETRIES: u8 = 3;
+
+/// What to do when a sender-response/PS-transition timer expires after `retries` prior attempts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RetryOutcome {
+    /// Resend the last Request, having used up one more retry.
+    Retry { retries: u8 },
+    /// Retries are exhausted; issue a Hard Reset instead.
+    HardReset,
+}
+
+fn retry_outcome(retries: u8) -> RetryOutcome {
+    if retries >= MAX_RETRIES {
+        RetryOutcome::HardReset
+    } else {
+        RetryOutcome::Retry {
+            retries: retries + 1,
+        }
+    }
+}
+
+/// A currently-active power contract, as seen by a `RequestPolicy`.
+#[derive(Clone, Copy)]
+pub struct ActiveContract {
+    pub voltage_mv: u16,
+    pub max_current_ma: u16,
+}
+
+/// What a `RequestPolicy` decided to request in response to (new) `SourceCapabilities`.
+pub enum PowerRequest {
+    /// Request the fixed supply at `caps.pdos()[pdo_index]`. Out-of-range indices, and indices
+    /// of any other PDO variant, are treated as `NoChange` rather than panicking.
+    RequestPower { pdo_index: usize, current_ma: u16 },
+    /// Request a PPS output from the APDO at `caps.pdos()[pdo_index]`. Rejected the same way as
+    /// `RequestPower` if `pdo_index` isn't a PPS APDO whose range contains `voltage_mv`.
+    RequestPps {
+        pdo_index: usize,
+        voltage_mv: u16,
+        current_ma: u16,
+    },
+    /// Keep whatever contract (if any) is currently active.
+    NoChange,
+}
+
+/// Decides which power to request out of a source's advertised capabilities. Implement this to
+/// customize power selection without forking the crate.
+pub trait RequestPolicy {
+    fn decide(
+        &mut self,
+        caps: &SourceCapabilities,
+        current: Option<ActiveContract>,
+    ) -> PowerRequest;
+}
+
+/// Requests the highest-voltage fixed supply advertised, at its full rated current. This is the
+/// sink's default policy.
+#[derive(Clone, Copy, Default)]
+pub struct HighestVoltage;
+
+impl RequestPolicy for HighestVoltage {
+    fn decide(
+        &mut self,
+        caps: &SourceCapabilities,
+        _current: Option<ActiveContract>,
+    ) -> PowerRequest {
+        let Some((index, supply)) = caps
+            .pdos()
+            .iter()
+            .enumerate()
+            .filter_map(|(i, cap)| match cap {
+                PowerDataObject::FixedSupply(supply) => Some((i, supply)),
+                _ => None,
+            })
+            .max_by_key(|(_, supply)| supply.voltage_mv())
+        else {
+            return PowerRequest::NoChange;
+        };
+
+        PowerRequest::RequestPower {
+            pdo_index: index,
+            current_ma: supply.max_current_ma(),
+        }
+    }
+}
+
+/// Requests a specific fixed voltage, falling back to `HighestVoltage` if the source doesn't
+/// advertise it.
+pub struct FixedVoltage {
+    pub target_mv: u16,
+    pub fallback: HighestVoltage,
+}
+
+impl RequestPolicy for FixedVoltage {
+    fn decide(
+        &mut self,
+        caps: &SourceCapabilities,
+        current: Option<ActiveContract>,
+    ) -> PowerRequest {
+        let target = caps
+            .pdos()
+            .iter()
+            .enumerate()
+            .find_map(|(i, cap)| match cap {
+                PowerDataObject::FixedSupply(supply) if supply.voltage_mv() == self.target_mv => {
+                    Some((i, supply))
+                }
+                _ => None,
+            });
+
+        match target {
+            Some((index, supply)) => PowerRequest::RequestPower {
+                pdo_index: index,
+                current_ma: supply.max_current_ma(),
+            },
+            None => self.fallback.decide(caps, current),
+        }
+    }
+}
+
+/// Policy-engine state, tracking what we're currently waiting on and since when.
+#[derive(Clone, Copy)]
+enum PolicyState {
+    /// No request outstanding
+    Ready,
+    /// Waiting for the first SourceCapabilities after entering `UsbPd`
+    WaitingForCapabilities { armed_at: Instant },
+    /// Sent a Request, waiting for Accept/Reject
+    WaitingForResponse { armed_at: Instant, retries: u8 },
+    /// Accepted, waiting for PS_RDY
+    WaitingForPsRdy { armed_at: Instant, retries: u8 },
+}
+
 pub trait Driver {
     fn init(&mut self);
 
@@ -19,6 +206,9 @@ pub trait Driver {
     fn send_message(&mut self, header: Header, payload: &[u8]);
 
     fn state(&mut self) -> State;
+
+    /// Issues a Hard Reset, tearing down the USB PD contract and restarting negotiation.
+    fn request_hard_reset(&mut self);
 }
 
 /// FUSB302 state
@@ -40,10 +230,14 @@ pub enum Event {
     MessageReceived(Message),
 }
 
-pub struct Sink<DRIVER> {
+pub struct Sink<DRIVER, POLICY = HighestVoltage> {
     pd_controller: DRIVER,
+    policy: POLICY,
     protocol_: Protocol,
 
+    /// Whether a PD contract is currently active (as opposed to the unnegotiated 5V default)
+    has_contract: bool,
+
     /// Requested voltage (in mV)
     requested_voltage: u16,
 
@@ -56,30 +250,84 @@ pub struct Sink<DRIVER> {
     /// Active maximum current (in mA)
     active_max_current: u16,
 
+    /// Capability flags from the last SourceCapabilities message
+    source_flags: Option<SourceCapabilityFlags>,
+
     /// Specification revision (of last message)
     spec_rev: u8,
+
+    /// PPS request awaiting Accept and PS_RDY, if one was last sent
+    pending_pps: Option<PpsRequest>,
+
+    /// PPS request currently in effect, requiring periodic re-requests to stay alive
+    active_pps: Option<PpsRequest>,
+
+    /// When the active PPS request was last (re-)sent
+    pps_last_request: Option<Instant>,
+
+    /// Policy-engine state (timers and retry bookkeeping)
+    policy_state: PolicyState,
+
+    /// The last Request we sent, for resending verbatim on a timeout
+    last_request: Option<LastRequest>,
 }
 
-impl<DRIVER: Driver> Sink<DRIVER> {
+impl<DRIVER: Driver> Sink<DRIVER, HighestVoltage> {
+    /// Creates a sink that requests the highest-voltage fixed supply advertised, matching the
+    /// crate's previous, non-configurable behavior. Use `with_policy` to customize selection.
     pub fn new(driver: DRIVER) -> Self {
+        Self::with_policy(driver, HighestVoltage)
+    }
+}
+
+impl<DRIVER: Driver, POLICY: RequestPolicy> Sink<DRIVER, POLICY> {
+    pub fn with_policy(driver: DRIVER, policy: POLICY) -> Self {
         Self {
             pd_controller: driver,
+            policy,
             protocol_: Protocol::Usb20,
+            has_contract: false,
 
             requested_voltage: 0,
             requested_max_current: 0,
             active_voltage: 5000,
             active_max_current: 900,
+            source_flags: None,
             spec_rev: 1,
+
+            pending_pps: None,
+            active_pps: None,
+            pps_last_request: None,
+
+            policy_state: PolicyState::Ready,
+            last_request: None,
         }
     }
 
-    pub fn init(&mut self) {
+    pub fn init(&mut self, now: Instant) {
         self.pd_controller.init();
-        self.update_protocol();
+        self.update_protocol(now);
     }
 
     pub fn poll(&mut self, now: Instant) {
+        // PPS output is only held while we keep re-requesting it, otherwise the source reverts
+        // to 5V after its PPS timeout expires.
+        if let Some(pps) = self.active_pps {
+            let due = self.pps_last_request.map_or(true, |last| {
+                elapsed_ms(now, last) >= PPS_REQUEST_INTERVAL_MS
+            });
+
+            if due {
+                // Route the renewal through the same bookkeeping as the initial request so
+                // `pending_pps`/`requested_voltage`/`requested_max_current` are repopulated —
+                // otherwise the next `Ready` wipes `active_pps` and collapses `active_voltage`
+                // to 0 instead of re-confirming the still-active PPS contract.
+                self.start_pps_request(pps, now);
+            }
+        }
+
+        self.poll_policy_timers(now);
+
         // process events from PD controller
         loop {
             self.pd_controller.poll(now);
@@ -90,96 +338,299 @@ impl<DRIVER: Driver> Sink<DRIVER> {
 
             match evt {
                 Event::StateChanged => {
-                    if self.update_protocol() {
-                        self.notify(CallbackEvent::ProtocolChanged);
+                    if self.update_protocol(now) {
+                        self.notify(CallbackEvent::ProtocolChanged, now);
                     }
                 }
                 Event::MessageReceived(message) => {
-                    self.handle_msg(message);
+                    self.handle_msg(message, now);
+                }
+            }
+        }
+    }
+
+    /// Checks the currently armed policy-engine timer, retrying or escalating to a Hard Reset
+    /// on expiry.
+    fn poll_policy_timers(&mut self, now: Instant) {
+        match self.policy_state {
+            PolicyState::Ready => {}
+            PolicyState::WaitingForCapabilities { armed_at } => {
+                if elapsed_ms(now, armed_at) >= SINK_WAIT_CAP_TIMEOUT_MS {
+                    self.hard_reset(now);
+                }
+            }
+            PolicyState::WaitingForResponse { armed_at, retries } => {
+                if elapsed_ms(now, armed_at) >= SENDER_RESPONSE_TIMEOUT_MS {
+                    self.retry_or_hard_reset(retries, now);
+                }
+            }
+            PolicyState::WaitingForPsRdy { armed_at, retries } => {
+                if elapsed_ms(now, armed_at) >= PS_TRANSITION_TIMEOUT_MS {
+                    self.retry_or_hard_reset(retries, now);
                 }
             }
         }
     }
 
-    fn update_protocol(&mut self) -> bool {
+    fn retry_or_hard_reset(&mut self, retries: u8, now: Instant) {
+        let retries = match retry_outcome(retries) {
+            RetryOutcome::HardReset => {
+                self.hard_reset(now);
+                return;
+            }
+            RetryOutcome::Retry { retries } => retries,
+        };
+
+        match self.last_request {
+            Some(LastRequest::Fixed {
+                index,
+                voltage_mv,
+                current_ma,
+            }) => self.send_fixed_request(index, voltage_mv, current_ma),
+            Some(LastRequest::Pps(pps)) => self.send_pps_request(pps, now),
+            None => {}
+        }
+
+        self.policy_state = PolicyState::WaitingForResponse {
+            armed_at: now,
+            retries,
+        };
+    }
+
+    /// Tears the contract down to the 5V/900mA default and restarts capability negotiation.
+    fn hard_reset(&mut self, now: Instant) {
+        self.pd_controller.request_hard_reset();
+
+        self.active_voltage = 5000;
+        self.active_max_current = 900;
+        self.requested_voltage = 0;
+        self.requested_max_current = 0;
+        self.pending_pps = None;
+        self.active_pps = None;
+        self.has_contract = false;
+        self.source_flags = None;
+        self.last_request = None;
+        self.policy_state = PolicyState::WaitingForCapabilities { armed_at: now };
+
+        self.notify(CallbackEvent::HardReset, now);
+    }
+
+    fn update_protocol(&mut self, now: Instant) -> bool {
         let old_protocol = self.protocol_;
 
         if self.pd_controller.state() == State::UsbPd {
             self.protocol_ = Protocol::UsbPd;
+
+            if old_protocol != Protocol::UsbPd {
+                self.policy_state = PolicyState::WaitingForCapabilities { armed_at: now };
+            }
         } else {
             self.protocol_ = Protocol::Usb20;
             self.active_voltage = 5000;
             self.active_max_current = 900;
+            self.requested_voltage = 0;
+            self.requested_max_current = 0;
+            self.pending_pps = None;
+            self.active_pps = None;
+            self.pps_last_request = None;
+            self.has_contract = false;
+            self.source_flags = None;
+            self.last_request = None;
+            self.policy_state = PolicyState::Ready;
         }
 
         return self.protocol_ != old_protocol;
     }
 
-    fn handle_msg(&mut self, message: Message) {
+    fn handle_msg(&mut self, message: Message, now: Instant) {
         match message {
-            Message::Accept => self.notify(CallbackEvent::PowerAccepted),
+            Message::Accept => {
+                self.policy_state = PolicyState::WaitingForPsRdy {
+                    armed_at: now,
+                    retries: 0,
+                };
+                self.notify(CallbackEvent::PowerAccepted, now);
+            }
             Message::Reject => {
                 self.requested_voltage = 0;
                 self.requested_max_current = 0;
-                self.notify(CallbackEvent::PowerRejected);
+                self.pending_pps = None;
+                self.policy_state = PolicyState::Ready;
+                self.notify(CallbackEvent::PowerRejected, now);
             }
             Message::Ready => {
                 self.active_voltage = self.requested_voltage;
                 self.active_max_current = self.requested_max_current;
                 self.requested_voltage = 0;
                 self.requested_max_current = 0;
-                self.notify(CallbackEvent::PowerReady);
+                self.active_pps = self.pending_pps.take();
+                self.has_contract = true;
+                self.policy_state = PolicyState::Ready;
+                self.notify(CallbackEvent::PowerReady, now);
             }
             Message::SourceCapabilities(caps) => {
-                self.notify(CallbackEvent::SourceCapabilitiesChanged(caps))
+                self.policy_state = PolicyState::Ready;
+                self.source_flags = caps.flags();
+
+                if let Some(flags) = self.source_flags {
+                    self.notify(CallbackEvent::SourceFlagsChanged(flags), now);
+                }
+
+                self.notify(CallbackEvent::SourceCapabilitiesChanged(caps), now)
             }
-            Message::Unknown => unimplemented!(),
+            Message::VendorDefined((header, vdos)) => {
+                if let Some(identity) = Identity::parse(&header, &vdos) {
+                    self.notify(CallbackEvent::IdentityDiscovered(identity), now);
+                }
+            }
+            Message::SoftReset => {
+                self.last_request = None;
+                self.pending_pps = None;
+                self.send_accept();
+                self.policy_state = PolicyState::WaitingForCapabilities { armed_at: now };
+            }
+            Message::Unknown => {}
         }
     }
 
-    fn notify(&mut self, event: CallbackEvent) {
+    fn send_accept(&mut self) {
+        let header = Header(0)
+            .with_message_type_raw(ControlMessageType::Accept as u8)
+            .with_num_objects(0)
+            .with_spec_revision(SpecificationRevision::from(self.spec_rev))
+            .with_port_power_role(PowerRole::Sink);
+
+        self.pd_controller.send_message(header, &[]);
+    }
+
+    fn notify(&mut self, event: CallbackEvent, now: Instant) {
         match event {
             CallbackEvent::SourceCapabilitiesChanged(caps) => {
-                debug!("Caps changed: {}", caps.len());
-
-                // Take maximum voltage
-                let (index, supply) = caps
-                    .iter()
-                    .enumerate()
-                    .filter_map(|(i, cap)| {
-                        if let PowerDataObject::FixedSupply(supply) = cap {
-                            trace!(
-                                "supply @ {}: {}mV {}mA",
-                                i,
-                                supply.voltage() * 50,
-                                supply.max_current() * 10
-                            );
-                            Some((i, supply))
-                        } else {
-                            None
+                debug!("Caps changed: {}", caps.pdos().len());
+
+                let current = self.has_contract.then_some(ActiveContract {
+                    voltage_mv: self.active_voltage,
+                    max_current_ma: self.active_max_current,
+                });
+
+                match self.policy.decide(&caps, current) {
+                    PowerRequest::RequestPower {
+                        pdo_index,
+                        current_ma,
+                    } => {
+                        let Some(PowerDataObject::FixedSupply(supply)) = caps.pdos().get(pdo_index)
+                        else {
+                            // Policy returned an out-of-range index or a non-Fixed-Supply PDO;
+                            // treat it the same as `NoChange` rather than panicking.
+                            return;
+                        };
+
+                        trace!("requesting {:?}@{}", supply, pdo_index);
+
+                        self.request_power(supply.voltage_mv(), current_ma, pdo_index + 1, now);
+                    }
+                    PowerRequest::RequestPps {
+                        pdo_index,
+                        voltage_mv,
+                        current_ma,
+                    } => {
+                        if !pps_in_range(&caps, pdo_index, voltage_mv) {
+                            // Same as above: an invalid index/voltage from the policy is a no-op.
+                            return;
                         }
-                    })
-                    .max_by(|(_, x), (_, y)| x.voltage().cmp(&y.voltage()))
-                    .unwrap();
-
-                trace!("supply {:?}@{}", supply, index);
 
-                self.request_power(supply.voltage() * 50, supply.max_current() * 10, index + 1);
+                        trace!(
+                            "requesting PPS {}mV@{} from {}",
+                            voltage_mv,
+                            current_ma,
+                            pdo_index
+                        );
+                        self.start_pps_request(
+                            PpsRequest {
+                                index: (pdo_index + 1) as u8,
+                                voltage_mv,
+                                current_ma,
+                            },
+                            now,
+                        );
+                    }
+                    PowerRequest::NoChange => {}
+                }
             }
 
             CallbackEvent::PowerReady => debug!("Voltage: {}", self.active_voltage),
 
             CallbackEvent::ProtocolChanged => debug!("protocol_changed"),
 
+            CallbackEvent::IdentityDiscovered(identity) => {
+                debug!("Identity: VID {:x}", identity.id_header.usb_vendor_id())
+            }
+
+            CallbackEvent::HardReset => debug!("hard reset"),
+
+            CallbackEvent::SourceFlagsChanged(flags) => {
+                debug!(
+                    "Source flags: dual_role_power={}, unconstrained={}",
+                    flags.dual_role_power, flags.unconstrained_power
+                )
+            }
+
             _ => (),
         }
     }
 
-    fn request_power(&mut self, voltage: u16, max_current: u16, index: usize) {
+    /// Capability flags from the most recent SourceCapabilities message, or `None` before the
+    /// first one has arrived. An unconstrained source is safe to draw full power from
+    /// immediately; a dual-role partner may itself be battery-backed.
+    pub fn source_capability_flags(&self) -> Option<SourceCapabilityFlags> {
+        self.source_flags
+    }
+
+    /// Sends a Discover Identity REQ, asking the source to identify itself. The response (if
+    /// any) arrives as a `CallbackEvent::IdentityDiscovered` once the ACK is received.
+    pub fn discover_identity(&mut self) {
+        let vdm_header = VDMHeader(0)
+            .with_vdm_type(VDMType::Structured)
+            .with_svid(SVID_DISCOVERY)
+            .with_command_type(VDMCommandType::Req)
+            .with_command(VDMCommand::DiscoverIdentity);
+
+        let mut payload = [0; 4];
+        LittleEndian::write_u32(&mut payload, vdm_header.0);
+
+        let header = Header(0)
+            .with_message_type_raw(DataMessageType::VendorDefined as u8)
+            .with_num_objects(1)
+            .with_spec_revision(SpecificationRevision::from(self.spec_rev))
+            .with_port_power_role(PowerRole::Sink);
+
+        self.pd_controller.send_message(header, &payload);
+    }
+
+    fn request_power(&mut self, voltage: u16, max_current: u16, index: usize, now: Instant) {
+        self.pending_pps = None;
+        self.active_pps = None;
+        self.pps_last_request = None;
+        self.requested_voltage = voltage;
+        self.requested_max_current = max_current;
+        self.send_fixed_request(index as u8, voltage, max_current);
+
+        self.last_request = Some(LastRequest::Fixed {
+            index: index as u8,
+            voltage_mv: voltage,
+            current_ma: max_current,
+        });
+        self.policy_state = PolicyState::WaitingForResponse {
+            armed_at: now,
+            retries: 0,
+        };
+    }
+
+    fn send_fixed_request(&mut self, index: u8, voltage: u16, max_current: u16) {
         // Create 'request' message
         let mut payload = [0; 4];
 
-        self.set_request_payload_fixed(&mut payload, index as u8, voltage, max_current);
+        set_request_payload_fixed(&mut payload, index, voltage, max_current);
 
         let header = Header(0)
             .with_message_type_raw(DataMessageType::Request as u8)
@@ -191,26 +642,170 @@ impl<DRIVER: Driver> Sink<DRIVER> {
         self.pd_controller.send_message(header, &payload);
     }
 
-    fn set_request_payload_fixed(
+    /// Requests a PPS output close to `voltage_mv`, scanning `caps` for an APDO whose range
+    /// contains it. Returns `false` if no matching APDO was advertised.
+    pub fn request_pps(
         &mut self,
-        payload: &mut [u8],
-        obj_pos: u8,
-        _voltage: u16,
-        mut current: u16,
-    ) {
-        current = (current + 5) / 10;
-
-        if current > 0x3ff {
-            current = 0x3ff;
-        }
+        caps: &SourceCapabilities,
+        now: Instant,
+        voltage_mv: u16,
+        current_ma: u16,
+    ) -> bool {
+        let Some(index) = (0..caps.pdos().len()).find(|&i| pps_in_range(caps, i, voltage_mv))
+        else {
+            return false;
+        };
+
+        self.start_pps_request(
+            PpsRequest {
+                index: (index + 1) as u8,
+                voltage_mv,
+                current_ma,
+            },
+            now,
+        );
+
+        true
+    }
+
+    fn start_pps_request(&mut self, request: PpsRequest, now: Instant) {
+        self.pending_pps = Some(request);
+        self.requested_voltage = request.voltage_mv;
+        self.requested_max_current = request.current_ma;
+        self.send_pps_request(request, now);
+
+        self.last_request = Some(LastRequest::Pps(request));
+        self.policy_state = PolicyState::WaitingForResponse {
+            armed_at: now,
+            retries: 0,
+        };
+    }
+
+    fn send_pps_request(&mut self, pps: PpsRequest, now: Instant) {
+        let mut payload = [0; 4];
+
+        set_request_payload_pps(&mut payload, pps.index, pps.voltage_mv, pps.current_ma);
+
+        let header = Header(0)
+            .with_message_type_raw(DataMessageType::Request as u8)
+            .with_num_objects(1)
+            .with_spec_revision(SpecificationRevision::from(self.spec_rev))
+            .with_port_power_role(PowerRole::Sink);
+
+        self.pd_controller.send_message(header, &payload);
+        self.pps_last_request = Some(now);
+    }
+
+}
+
+/// Encodes a Fixed/Variable Supply Request Data Object, clamping `current` (in mA) to the field's
+/// 10mA-unit range.
+fn set_request_payload_fixed(payload: &mut [u8], obj_pos: u8, _voltage: u16, mut current: u16) {
+    current = current.saturating_add(5) / 10;
+
+    if current > 0x3ff {
+        current = 0x3ff;
+    }
+
+    FixedVariableRequestDataObject(0)
+        .with_operating_current(current)
+        .with_maximum_operating_current(current)
+        .with_object_position(obj_pos)
+        .with_no_usb_suspend(true)
+        .with_usb_communications_capable(true)
+        .to_bytes(payload);
+}
+
+/// Encodes a Programmable Request Data Object, clamping `voltage_mv`/`current_ma` to the fields'
+/// 20mV/50mA-unit ranges.
+fn set_request_payload_pps(payload: &mut [u8], obj_pos: u8, voltage_mv: u16, mut current_ma: u16) {
+    let mut voltage = voltage_mv / 20;
+
+    if voltage > 0x7ff {
+        voltage = 0x7ff;
+    }
+
+    current_ma = current_ma.saturating_add(25) / 50;
+
+    if current_ma > 0x7f {
+        current_ma = 0x7f;
+    }
+
+    ProgrammableRequestDataObject(0)
+        .with_operating_current(current_ma)
+        .with_output_voltage(voltage)
+        .with_object_position(obj_pos)
+        .with_no_usb_suspend(true)
+        .with_usb_communications_capable(true)
+        .to_bytes(payload);
+}
+
+#[cfg(test)]
+mod retry_tests {
+    use super::*;
+
+    #[test]
+    fn retries_below_max_are_incremented() {
+        assert_eq!(
+            retry_outcome(0),
+            RetryOutcome::Retry { retries: 1 }
+        );
+        assert_eq!(
+            retry_outcome(MAX_RETRIES - 1),
+            RetryOutcome::Retry { retries: MAX_RETRIES }
+        );
+    }
+
+    #[test]
+    fn retries_at_or_above_max_hard_reset() {
+        assert_eq!(retry_outcome(MAX_RETRIES), RetryOutcome::HardReset);
+        assert_eq!(retry_outcome(MAX_RETRIES + 1), RetryOutcome::HardReset);
+    }
+}
+
+#[cfg(test)]
+mod payload_tests {
+    use super::*;
+
+    #[test]
+    fn fixed_request_clamps_current_to_field_max() {
+        let mut payload = [0; 4];
+        set_request_payload_fixed(&mut payload, 1, 20_000, 60_000);
+
+        let raw = FixedVariableRequestDataObject(LittleEndian::read_u32(&payload));
+        assert_eq!(raw.operating_current(), 0x3ff);
+        assert_eq!(raw.maximum_operating_current(), 0x3ff);
+        assert_eq!(raw.object_position(), 1);
+    }
+
+    #[test]
+    fn fixed_request_rounds_current_to_nearest_10ma() {
+        let mut payload = [0; 4];
+        set_request_payload_fixed(&mut payload, 1, 5_000, 1_234);
+
+        let raw = FixedVariableRequestDataObject(LittleEndian::read_u32(&payload));
+        assert_eq!(raw.operating_current(), 123);
+    }
+
+    #[test]
+    fn pps_request_clamps_voltage_and_current_to_field_max() {
+        let mut payload = [0; 4];
+        set_request_payload_pps(&mut payload, 2, 60_000, 10_000);
+
+        let raw = ProgrammableRequestDataObject(LittleEndian::read_u32(&payload));
+        assert_eq!(raw.output_voltage(), 0x7ff);
+        assert_eq!(raw.operating_current(), 0x7f);
+        assert_eq!(raw.object_position(), 2);
+    }
+
+    #[test]
+    fn pps_request_rounds_voltage_and_current() {
+        let mut payload = [0; 4];
+        set_request_payload_pps(&mut payload, 2, 5_000, 1_000);
 
-        FixedVariableRequestDataObject(0)
-            .with_operating_current(current)
-            .with_maximum_operating_current(current)
-            .with_object_position(obj_pos as u8)
-            .with_no_usb_suspend(true)
-            .with_usb_communications_capable(true)
-            .to_bytes(payload);
+        let raw = ProgrammableRequestDataObject(LittleEndian::read_u32(&payload));
+        assert_eq!(raw.output_voltage(), 250);
+        assert_eq!(raw.operating_current(), 20);
     }
 }
 
@@ -241,11 +836,18 @@ enum CallbackEvent {
     /// Power delivery protocol has changed
     ProtocolChanged,
     /// Source capabilities have changed (immediately request power)
-    SourceCapabilitiesChanged(Vec<PowerDataObject, 8>),
+    SourceCapabilitiesChanged(SourceCapabilities),
     /// Requested power has been accepted (but not ready yet)
     PowerAccepted,
     /// Requested power has been rejected
     PowerRejected,
     /// Requested power is now ready
     PowerReady,
+    /// Source responded to a Discover Identity request
+    IdentityDiscovered(Identity),
+    /// A Hard Reset was issued, after the policy engine exhausted its retries or timed out
+    /// waiting for SourceCapabilities
+    HardReset,
+    /// The source's capability flags (dual-role, unconstrained power, etc.) have changed
+    SourceFlagsChanged(SourceCapabilityFlags),
 }